@@ -1,13 +1,16 @@
 use std::io::Write;
-use rand::seq::SliceRandom;
 use std::borrow::{Borrow, BorrowMut};
+use rand::seq::SliceRandom;
+use serde::{Serialize, Deserialize};
 
 use std::rc::Rc;
 use std::cell::RefCell;
 
-use stdweb::web::{document, INode, IEventTarget, Element, IElement, Document};
-use stdweb::web::event::ClickEvent;
+use stdweb::web::{document, INode, IEventTarget, Element, IElement, Document, WebSocket};
+use stdweb::web::event::{ClickEvent, SocketOpenEvent, SocketMessageEvent, IMessageEvent, SocketMessageData};
 use stdweb::web::error::InvalidCharacterError;
+use stdweb::web::html_element::InputElement;
+use stdweb::unstable::TryInto;
 
 struct Reactive<T> {
     inner: T,
@@ -88,7 +91,7 @@ impl<T> Reactive<T> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 struct Light {
     status: bool,
 }
@@ -105,16 +108,20 @@ impl Light {
     }
 }
 
-fn light_widget(doc: &Document, game: Rc<RefCell<Game>>, row: usize, col: usize) -> Result<Element, InvalidCharacterError> {
+fn set_text(element: &Element, text: &str) {
+    while let Some(child) = element.first_child() {
+        element.remove_child(&child).unwrap();
+    }
+    element.append_child(&document().create_text_node(text));
+}
+
+// renders cell `(row, col)` as a div that stays in sync with its light
+fn light_display_widget(doc: &Document, game: Rc<RefCell<Game>>, row: usize, col: usize) -> Result<Element, InvalidCharacterError> {
     let button = doc.create_element("div")?;
     let button_clone = button.clone();
 
-    let game_clone = game.clone();
-    button.add_event_listener(move |_: ClickEvent| {
-        RefCell::borrow_mut(&game_clone).make_move(row, col);
-    });
-
-    RefCell::borrow_mut(&game).lights[row * 5 + col].register(move |light| {
+    let cols = RefCell::borrow(&game).cols;
+    RefCell::borrow_mut(&game).lights[row * cols + col].register(move |light| {
         while let Some(child) = button.first_child() {
             button.remove_child(&child).unwrap();
         }
@@ -129,6 +136,147 @@ fn light_widget(doc: &Document, game: Rc<RefCell<Game>>, row: usize, col: usize)
     Ok(button_clone)
 }
 
+fn light_widget(doc: &Document, game: Rc<RefCell<Game>>, row: usize, col: usize) -> Result<Element, InvalidCharacterError> {
+    let button = light_display_widget(doc, game.clone(), row, col)?;
+
+    let game_clone = game.clone();
+    button.add_event_listener(move |_: ClickEvent| {
+        RefCell::borrow_mut(&game_clone).make_move(row, col);
+    });
+
+    Ok(button)
+}
+
+fn hint_widget(doc: &Document, game: Rc<RefCell<Game>>) -> Result<Element, InvalidCharacterError> {
+    let container = doc.create_element("span")?;
+
+    let button = doc.create_element("button")?;
+    button.append_child(&doc.create_text_node("Hint"));
+    container.append_child(&button);
+
+    let message = doc.create_element("span")?;
+    container.append_child(&message);
+
+    let message_clone = message.clone();
+    let game_clone = game.clone();
+    button.add_event_listener(move |_: ClickEvent| {
+        while let Some(child) = message_clone.first_child() {
+            message_clone.remove_child(&child).unwrap();
+        }
+
+        let text = {
+            let game = RefCell::borrow(&game_clone);
+            match game.hint() {
+                Some(i) => format!(" Press row {}, column {}", i / game.cols, i % game.cols),
+                None => " No solution exists for this board".to_string(),
+            }
+        };
+        message_clone.append_child(&document().create_text_node(&text));
+    });
+
+    Ok(container)
+}
+
+fn solve_widget(doc: &Document, game: Rc<RefCell<Game>>) -> Result<Element, InvalidCharacterError> {
+    let button = doc.create_element("button")?;
+    button.append_child(&doc.create_text_node("Solve"));
+
+    let game_clone = game.clone();
+    button.add_event_listener(move |_: ClickEvent| {
+        let solution = RefCell::borrow(&game_clone).solve();
+        let cols = RefCell::borrow(&game_clone).cols;
+        if let Some(cells) = solution {
+            for i in cells {
+                RefCell::borrow_mut(&game_clone).make_move(i / cols, i % cols);
+            }
+        }
+    });
+
+    Ok(button)
+}
+
+// undo isn't broadcast (redo is, through `last_move`), so both are disabled
+// once a race starts to avoid desyncing the two boards
+fn undo_widget(doc: &Document, game: Rc<RefCell<Game>>, state: Rc<RefCell<GameState>>) -> Result<Element, InvalidCharacterError> {
+    let button = doc.create_element("button")?;
+    button.append_child(&doc.create_text_node("Undo"));
+
+    let game_clone = game.clone();
+    button.add_event_listener(move |_: ClickEvent| {
+        if let GameState::NetworkedMultiplayer { .. } = &*RefCell::borrow(&state) {
+            return;
+        }
+        RefCell::borrow_mut(&game_clone).undo();
+    });
+
+    Ok(button)
+}
+
+fn redo_widget(doc: &Document, game: Rc<RefCell<Game>>, state: Rc<RefCell<GameState>>) -> Result<Element, InvalidCharacterError> {
+    let button = doc.create_element("button")?;
+    button.append_child(&doc.create_text_node("Redo"));
+
+    let game_clone = game.clone();
+    button.add_event_listener(move |_: ClickEvent| {
+        if let GameState::NetworkedMultiplayer { .. } = &*RefCell::borrow(&state) {
+            return;
+        }
+        RefCell::borrow_mut(&game_clone).redo();
+    });
+
+    Ok(button)
+}
+
+fn share_widget(doc: &Document, game: Rc<RefCell<Game>>) -> Result<Element, InvalidCharacterError> {
+    let container = doc.create_element("span")?;
+
+    let code_input: InputElement = doc.create_element("input")?.try_into().unwrap();
+    code_input.set_raw_value(&RefCell::borrow(&game).to_code());
+    code_input.set_attribute("readonly", "")?;
+    container.append_child(&code_input);
+
+    // `moves` fires while `game` is still borrowed, so mirror the bits
+    // `to_code` needs instead of re-borrowing `game` from the listener.
+    let cache = Rc::new(RefCell::new(GameData::from(&*RefCell::borrow(&game))));
+
+    let light_count = RefCell::borrow(&game).lights.len();
+    for i in 0..light_count {
+        let cache = cache.clone();
+        let code_input = code_input.clone();
+        RefCell::borrow_mut(&game).lights[i].register(move |light| {
+            RefCell::borrow_mut(&cache).lights[i].status = light.status;
+            code_input.set_raw_value(&RefCell::borrow(&cache).to_code());
+        });
+    }
+
+    let code_input_clone = code_input.clone();
+    RefCell::borrow_mut(&game).moves.register(move |&moves| {
+        RefCell::borrow_mut(&cache).moves = moves;
+        code_input_clone.set_raw_value(&RefCell::borrow(&cache).to_code());
+    });
+
+    Ok(container)
+}
+
+fn load_widget(doc: &Document, game: Rc<RefCell<Game>>) -> Result<Element, InvalidCharacterError> {
+    let container = doc.create_element("span")?;
+
+    let code_input: InputElement = doc.create_element("input")?.try_into().unwrap();
+    container.append_child(&code_input);
+
+    let button = doc.create_element("button")?;
+    button.append_child(&doc.create_text_node("Load from code"));
+    container.append_child(&button);
+
+    let code_input_clone = code_input.clone();
+    let game_clone = game.clone();
+    button.add_event_listener(move |_: ClickEvent| {
+        let _ = RefCell::borrow_mut(&game_clone).load_code(code_input_clone.raw_value().trim());
+    });
+
+    Ok(container)
+}
+
 fn moves_widget(doc: &Document, game: Rc<RefCell<Game>>) -> Result<Element, InvalidCharacterError> {
     let span = doc.create_element("span")?;
     let span_clone = span.clone();
@@ -145,81 +293,178 @@ fn moves_widget(doc: &Document, game: Rc<RefCell<Game>>) -> Result<Element, Inva
 
 #[derive(Debug, PartialEq, Eq)]
 struct Game {
-    lights: [Reactive<Light>; 25],
+    lights: Vec<Reactive<Light>>,
+    rows: usize,
+    cols: usize,
     moves: Reactive<usize>,
+    // Fires with the cell pressed by the most recent `make_move` or `redo`;
+    // `undo` and `load_code` reset it to `None`.
+    last_move: Reactive<Option<(usize, usize)>>,
+    // Cells undone, so `redo` can replay them; cleared on a fresh `make_move`.
+    redo_stack: Vec<usize>,
+}
+
+// `Reactive`'s listeners aren't data, so `Game` round-trips through this
+// plain-data shape to (de)serialize instead of deriving it directly.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct GameData {
+    rows: usize,
+    cols: usize,
+    lights: Vec<Light>,
+    moves: usize,
+}
+
+impl<'a> From<&'a Game> for GameData {
+    fn from(game: &'a Game) -> GameData {
+        GameData {
+            rows: game.rows,
+            cols: game.cols,
+            lights: game.lights.iter().map(|light| Light { status: light.inner.status }).collect(),
+            moves: *game.moves.borrow(),
+        }
+    }
+}
+
+impl From<GameData> for Game {
+    fn from(data: GameData) -> Game {
+        Game {
+            lights: data.lights.into_iter().map(Reactive::new).collect(),
+            rows: data.rows,
+            cols: data.cols,
+            moves: Reactive::new(data.moves),
+            last_move: Reactive::new(None),
+            redo_stack: vec![],
+        }
+    }
+}
+
+impl GameData {
+    // shared by `Game::to_code` and anything with only a `GameData` snapshot on hand
+    fn to_code(&self) -> String {
+        let bytes = serde_cbor::to_vec(self).expect("serializing a puzzle cannot fail");
+        base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD)
+    }
+}
+
+impl Serialize for Game {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GameData::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Game, D::Error> {
+        GameData::deserialize(deserializer).map(Game::from)
+    }
+}
+
+// like deriving it, but via `GameData` since `Reactive`'s listeners aren't `Clone`
+impl Clone for Game {
+    fn clone(&self) -> Game {
+        let mut game: Game = GameData::from(self).into();
+        game.last_move.inner = self.last_move.inner;
+        game.redo_stack = self.redo_stack.clone();
+        game
+    }
 }
 
 impl Game {
-    fn new_empty() -> Game {
+    fn new_empty(rows: usize, cols: usize) -> Game {
+        let mut lights = Vec::with_capacity(rows * cols);
+        for _ in 0..rows * cols {
+            lights.push(Reactive::new(Light::new()));
+        }
+
         Game {
-            lights: unsafe {
-                let mut lights: [Reactive<Light>; 25] =  std::mem::uninitialized();
-                for element in lights.iter_mut() {
-                    std::ptr::write(element, Reactive::new(Light::new()));
-                }
-                lights
-            },
+            lights,
+            rows,
+            cols,
             moves: Reactive::new(0),
+            last_move: Reactive::new(None),
+            redo_stack: vec![],
         }
     }
 
-    fn new_random<R: rand::Rng>(difficulty: usize, rng: &mut R) -> Game {
-        assert!(difficulty <= 25);
-        let mut game = Game::new_empty();
+    // Presses exactly `difficulty` random distinct cells from a solved board,
+    // retrying (up to a cap) for a board whose actual minimal solution --
+    // the returned `usize` -- matches, since a "quiet pattern" can sometimes
+    // cancel those presses down to a smaller one.
+    fn new_random<R: rand::Rng>(rows: usize, cols: usize, difficulty: usize, rng: &mut R) -> (Game, usize) {
+        let size = rows * cols;
+        assert!(difficulty <= size);
 
-        let mut toggles: [bool; 25] = [false; 25];
-        for i in 0..difficulty {
-            toggles[i] = true;
-        }
-        toggles.shuffle(rng);
+        const MAX_ATTEMPTS: usize = 10_000;
+        let mut best: Option<(Game, usize)> = None;
+        let mut cells: Vec<usize> = (0..size).collect();
 
-        for i in 0..25 {
-            if toggles[i] {
+        for _ in 0..MAX_ATTEMPTS {
+            cells.shuffle(rng);
+            let mut game = Game::new_empty(rows, cols);
+            for &i in &cells[..difficulty] {
                 game.toggle(i);
             }
+
+            let weight = match game.solve() {
+                Some(solution) => solution.len(),
+                None => continue,
+            };
+
+            if weight == difficulty {
+                return (game, weight);
+            }
+
+            let is_closer = best.as_ref().map_or(true, |&(_, best_weight)| {
+                let diff = (weight as isize - difficulty as isize).abs();
+                let best_diff = (best_weight as isize - difficulty as isize).abs();
+                diff < best_diff
+            });
+            if is_closer {
+                best = Some((game, weight));
+            }
         }
-        game
+
+        best.expect("a random board is always solvable, so at least one attempt succeeds")
     }
 
     fn toggle(&mut self, i: usize) {
-        assert!(i < 25);
+        assert!(i < self.lights.len());
 
-        let row = i / 5;
-        let col = i % 5;
+        let row = i / self.cols;
+        let col = i % self.cols;
 
         self.lights[i].lock().toggle();
 
         if row > 0 {
             self.toggle_rc(row - 1, col);
         }
-        if row < 4 {
+        if row < self.rows - 1 {
             self.toggle_rc(row + 1, col);
         }
         if col > 0 {
             self.toggle_rc(row, col - 1);
         }
-        if col < 4 {
+        if col < self.cols - 1 {
             self.toggle_rc(row, col + 1);
         }
     }
 
     fn toggle_rc(&mut self, row: usize, col: usize) {
-        assert!(row < 5);
-        assert!(col < 5);
+        assert!(row < self.rows);
+        assert!(col < self.cols);
 
-        self.lights[row * 5 + col].lock().toggle();
+        self.lights[row * self.cols + col].lock().toggle();
     }
 
     fn check_rc(&self, row: usize, col: usize) -> bool {
-        assert!(row < 5);
-        assert!(col < 5);
+        assert!(row < self.rows);
+        assert!(col < self.cols);
 
-        self.lights[row * 5 + col].inner.status
+        self.lights[row * self.cols + col].inner.status
     }
 
     fn all_off(&self) -> bool {
-        for i in 0..25 {
-            if self.lights[i].inner.status {
+        for light in &self.lights {
+            if light.inner.status {
                 return false;
             }
         }
@@ -228,18 +473,364 @@ impl Game {
 
     // like toggle_rc, but increments the move counter
     fn make_move(&mut self, row: usize, col: usize) {
-        self.toggle(row * 5 + col);
+        self.toggle(row * self.cols + col);
         *self.moves.lock() += 1;
+        *self.last_move.lock() = Some((row, col));
+        self.redo_stack.clear();
+    }
+
+    // like toggle, but pops the last move instead of pressing an arbitrary cell
+    fn undo(&mut self) -> bool {
+        let (row, col) = match *self.last_move.borrow() {
+            Some(cell) => cell,
+            None => return false,
+        };
+
+        let i = row * self.cols + col;
+        self.toggle(i);
+        *self.moves.lock() -= 1;
+        self.redo_stack.push(i);
+        *self.last_move.lock() = None;
+        true
+    }
+
+    // Replay the most recently undone move.
+    fn redo(&mut self) -> bool {
+        let i = match self.redo_stack.pop() {
+            Some(i) => i,
+            None => return false,
+        };
+
+        self.toggle(i);
+        *self.moves.lock() += 1;
+        *self.last_move.lock() = Some((i / self.cols, i % self.cols));
+        true
+    }
+
+    // cell `i`'s toggle pattern (itself + orthogonal neighbors) as a bitmask;
+    // column `i` of the GF(2) matrix `A` in `solve`
+    fn toggle_mask(rows: usize, cols: usize, i: usize) -> u64 {
+        let row = i / cols;
+        let col = i % cols;
+        let mut mask = 1 << i;
+        if row > 0 {
+            mask |= 1 << (i - cols);
+        }
+        if row < rows - 1 {
+            mask |= 1 << (i + cols);
+        }
+        if col > 0 {
+            mask |= 1 << (i - 1);
+        }
+        if col < cols - 1 {
+            mask |= 1 << (i + 1);
+        }
+        mask
     }
+
+    // solves `A x = b` over GF(2) for the cells to press, picking the fewest
+    // presses among the particular solution and its null-space "quiet patterns"
+    fn solve(&self) -> Option<Vec<usize>> {
+        let n = self.lights.len();
+        assert!(n <= 64, "solver only supports boards of up to 64 cells");
+
+        // Augmented matrix: `rows[i] = (coefficients, rhs)`.
+        let mut rows: Vec<(u64, bool)> = (0..n)
+            .map(|i| (Self::toggle_mask(self.rows, self.cols, i), self.lights[i].inner.status))
+            .collect();
+
+        // Gauss-Jordan elimination, tracking which column each pivot row
+        // corresponds to (columns with no pivot are free / null-space).
+        let mut pivot_row_for_col = vec![None; n];
+        let mut rank = 0;
+        for col in 0..n {
+            if let Some(p) = (rank..n).find(|&r| rows[r].0 & (1 << col) != 0) {
+                rows.swap(rank, p);
+                for r in 0..n {
+                    if r != rank && rows[r].0 & (1 << col) != 0 {
+                        rows[r].0 ^= rows[rank].0;
+                        rows[r].1 ^= rows[rank].1;
+                    }
+                }
+                pivot_row_for_col[col] = Some(rank);
+                rank += 1;
+            }
+        }
+
+        // A zero row with a nonzero RHS means no press combination clears b.
+        if rows[rank..].iter().any(|&(mask, rhs)| mask == 0 && rhs) {
+            return None;
+        }
+
+        // Particular solution: free variables set to 0.
+        let mut particular = vec![false; n];
+        for col in 0..n {
+            if let Some(r) = pivot_row_for_col[col] {
+                particular[col] = rows[r].1;
+            }
+        }
+
+        // One null-space "quiet pattern" per free column.
+        let free_cols: Vec<usize> = (0..n).filter(|&c| pivot_row_for_col[c].is_none()).collect();
+        let quiet_patterns: Vec<Vec<bool>> = free_cols
+            .iter()
+            .map(|&f| {
+                let mut pattern = vec![false; n];
+                pattern[f] = true;
+                for col in 0..n {
+                    if let Some(r) = pivot_row_for_col[col] {
+                        pattern[col] = (rows[r].0 >> f) & 1 != 0;
+                    }
+                }
+                pattern
+            })
+            .collect();
+
+        // Try every combination of quiet patterns added to the particular
+        // solution, keeping the one that presses the fewest cells. Capped so
+        // a large, highly-singular board can't force an exponential search.
+        let mut best = particular.clone();
+        let mut best_weight = best.iter().filter(|&&on| on).count();
+        if quiet_patterns.len() <= 20 {
+            for combo in 1..(1u32 << quiet_patterns.len()) {
+                let mut candidate = particular.clone();
+                for (k, pattern) in quiet_patterns.iter().enumerate() {
+                    if combo & (1 << k) != 0 {
+                        for i in 0..n {
+                            candidate[i] ^= pattern[i];
+                        }
+                    }
+                }
+                let weight = candidate.iter().filter(|&&on| on).count();
+                if weight < best_weight {
+                    best = candidate;
+                    best_weight = weight;
+                }
+            }
+        }
+
+        Some(
+            best.iter()
+                .enumerate()
+                .filter(|&(_, &on)| on)
+                .map(|(i, _)| i)
+                .collect(),
+        )
+    }
+
+    // Any one cell from the minimal solution, for a single-step nudge.
+    fn hint(&self) -> Option<usize> {
+        self.solve().and_then(|cells| cells.into_iter().next())
+    }
+
+    // a compact, URL-safe encoding of the board, for sharing/saving as plain text
+    fn to_code(&self) -> String {
+        GameData::from(self).to_code()
+    }
+
+    fn from_code(code: &str) -> Result<Game, Box<dyn std::error::Error>> {
+        let bytes = base64::decode_config(code, base64::URL_SAFE_NO_PAD)?;
+        let game = serde_cbor::from_slice(&bytes)?;
+        Ok(game)
+    }
+
+    // like `from_code`, but updates this board in place so its `Reactive`
+    // listeners keep firing; dimensions must match
+    fn load_code(&mut self, code: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let loaded = Game::from_code(code)?;
+        if loaded.rows != self.rows || loaded.cols != self.cols {
+            return Err(From::from("loaded puzzle has different dimensions"));
+        }
+
+        for (light, loaded_light) in self.lights.iter_mut().zip(loaded.lights.iter()) {
+            if light.inner.status != loaded_light.inner.status {
+                light.lock().toggle();
+            }
+        }
+        *self.moves.lock() = *loaded.moves.borrow();
+        *self.last_move.lock() = None;
+        self.redo_stack.clear();
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Local,
+    Remote,
+}
+
+// connection state for a head-to-head race
+enum GameState {
+    SinglePlayer,
+    NetworkedMultiplayer {
+        paired: bool,
+        winner: Option<Side>,
+    },
+}
+
+fn parse_move(s: &str) -> Option<(usize, usize)> {
+    let mut parts = s.splitn(2, ',');
+    let row = parts.next()?.parse().ok()?;
+    let col = parts.next()?.parse().ok()?;
+    Some((row, col))
+}
+
+fn declare_winner(state: &Rc<RefCell<GameState>>, status: &Element, side: Side) {
+    let mut state = RefCell::borrow_mut(state);
+    if let GameState::NetworkedMultiplayer { winner, .. } = &mut *state {
+        if winner.is_none() {
+            *winner = Some(side);
+            let message = match side {
+                Side::Local => "You win!",
+                Side::Remote => "Opponent wins!",
+            };
+            set_text(status, message);
+        }
+    }
+}
+
+// "Race!" button: races a WebSocket peer to clear the same puzzle first
+fn multiplayer_widget(doc: &Document, local_game: Rc<RefCell<Game>>, state: Rc<RefCell<GameState>>) -> Result<Element, InvalidCharacterError> {
+    let container = doc.create_element("div")?;
+
+    let url_input: InputElement = doc.create_element("input")?.try_into().unwrap();
+    url_input.set_raw_value("ws://localhost:8000");
+    container.append_child(&url_input);
+
+    let connect_button = doc.create_element("button")?;
+    connect_button.append_child(&doc.create_text_node("Race!"));
+    container.append_child(&connect_button);
+
+    let status = doc.create_element("span")?;
+    container.append_child(&status);
+
+    let remote_table = doc.create_element("table")?;
+    container.append_child(&remote_table);
+
+    // Holds whichever socket the last "Race!" click opened, so the move
+    // broadcast below (wired once) always talks to the current race.
+    let current_socket: Rc<RefCell<Option<WebSocket>>> = Rc::new(RefCell::new(None));
+
+    {
+        // `last_move` fires while `local_game` is still borrowed, so mirror
+        // the lit lights instead of re-borrowing it to check `all_off()`.
+        // Wired once here, not per "Race!" click, so moves aren't broadcast
+        // once per race played.
+        let light_count = RefCell::borrow(&local_game).lights.len();
+        let lit_cache = Rc::new(RefCell::new(
+            (0..light_count).map(|i| RefCell::borrow(&local_game).lights[i].inner.status).collect::<Vec<bool>>(),
+        ));
+        for i in 0..light_count {
+            let lit_cache = lit_cache.clone();
+            RefCell::borrow_mut(&local_game).lights[i].register(move |light| {
+                RefCell::borrow_mut(&lit_cache)[i] = light.status;
+            });
+        }
+
+        let current_socket = current_socket.clone();
+        let state = state.clone();
+        let status = status.clone();
+        RefCell::borrow_mut(&local_game).last_move.register(move |last_move| {
+            if let Some((row, col)) = *last_move {
+                if let GameState::NetworkedMultiplayer { paired: true, .. } = &*RefCell::borrow(&state) {
+                    if let Some(socket) = &*RefCell::borrow(&current_socket) {
+                        let _ = socket.send_text(&format!("MOVE:{},{}", row, col));
+                    }
+                }
+            }
+            if RefCell::borrow(&lit_cache).iter().all(|&on| !on) {
+                declare_winner(&state, &status, Side::Local);
+            }
+        });
+    }
+
+    let doc = doc.clone();
+    connect_button.add_event_listener(move |_: ClickEvent| {
+        let (rows, cols) = {
+            let game = RefCell::borrow(&local_game);
+            (game.rows, game.cols)
+        };
+        let remote_game = Rc::new(RefCell::new(Game::new_empty(rows, cols)));
+
+        while let Some(child) = remote_table.first_child() {
+            remote_table.remove_child(&child).unwrap();
+        }
+        for row in 0..rows {
+            let tr = doc.create_element("tr").unwrap();
+            remote_table.append_child(&tr);
+            for col in 0..cols {
+                let td = doc.create_element("td").unwrap();
+                tr.append_child(&td);
+                td.append_child(&light_display_widget(&doc, remote_game.clone(), row, col).unwrap());
+            }
+        }
+
+        let socket = WebSocket::new(&url_input.raw_value()).expect("could not open websocket");
+        *RefCell::borrow_mut(&current_socket) = Some(socket.clone());
+        *RefCell::borrow_mut(&state) = GameState::NetworkedMultiplayer {
+            paired: false,
+            winner: None,
+        };
+
+        {
+            let local_game = local_game.clone();
+            let inner_socket = socket.clone();
+            socket.add_event_listener(move |_: SocketOpenEvent| {
+                let code = RefCell::borrow(&local_game).to_code();
+                inner_socket.send_text(&format!("CODE:{}", code)).unwrap();
+            });
+        }
+
+        {
+            let local_game = local_game.clone();
+            let remote_game = remote_game.clone();
+            let state = state.clone();
+            let status = status.clone();
+            socket.add_event_listener(move |event: SocketMessageEvent| {
+                let text = match event.data() {
+                    SocketMessageData::Text(text) => text,
+                    _ => return,
+                };
+
+                if let Some(code) = text.strip_prefix("CODE:") {
+                    // Both sides send their own code on connect; load whichever
+                    // one sorts first as the shared seed both boards race from.
+                    let own_code = RefCell::borrow(&local_game).to_code();
+                    let shared_code = if code < own_code.as_str() { code } else { &own_code };
+                    let _ = RefCell::borrow_mut(&local_game).load_code(shared_code);
+                    let _ = RefCell::borrow_mut(&remote_game).load_code(shared_code);
+                    if let GameState::NetworkedMultiplayer { paired, .. } = &mut *RefCell::borrow_mut(&state) {
+                        *paired = true;
+                    }
+                    set_text(&status, "Paired! Race to clear your board.");
+                } else if let Some(rest) = text.strip_prefix("MOVE:") {
+                    if let Some((row, col)) = parse_move(rest) {
+                        RefCell::borrow_mut(&remote_game).make_move(row, col);
+
+                        if RefCell::borrow(&remote_game).all_off() {
+                            declare_winner(&state, &status, Side::Remote);
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    Ok(container)
 }
 
 impl std::fmt::Display for Game {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(fmt, " 01234")?;
+        write!(fmt, " ")?;
+        for col in 0..self.cols {
+            write!(fmt, "{}", col % 10)?;
+        }
 
-        for row in 0..5 {
-            write!(fmt, "\n{}", row)?;
-            for col in 0..5 {
+        for row in 0..self.rows {
+            write!(fmt, "\n{}", row % 10)?;
+            for col in 0..self.cols {
                 let c = if self.check_rc(row, col) {
                     '!'
                 } else {
@@ -255,7 +846,7 @@ impl std::fmt::Display for Game {
     }
 }
 
-fn read_usize(buffer: &mut String, stdin: &std::io::Stdin, stdout: &std::io::Stdout, label: &str) -> Result<usize, std::io::Error> {
+fn read_usize(buffer: &mut String, stdin: &std::io::Stdin, stdout: &std::io::Stdout, label: &str, bound: usize) -> Result<usize, std::io::Error> {
     loop {
         print!("{}", label);
         let mut stdout_lock = stdout.lock();
@@ -265,10 +856,10 @@ fn read_usize(buffer: &mut String, stdin: &std::io::Stdin, stdout: &std::io::Std
         let trimmed = buffer.trim();
         match trimmed.parse::<usize>() {
             Ok(x) => {
-                if x < 5 {
+                if x < bound {
                     return Ok(x);
                 } else {
-                    println!("You must enter a number between 0 and 4");
+                    println!("You must enter a number between 0 and {}", bound - 1);
                 }
             }
             Err(e) => {
@@ -280,7 +871,8 @@ fn read_usize(buffer: &mut String, stdin: &std::io::Stdin, stdout: &std::io::Std
 
 /*
 fn main() -> Result<(), std::io::Error> {
-    let mut game = Game::new_random(4, &mut rand::thread_rng());
+    let (mut game, par) = Game::new_random(5, 5, 4, &mut rand::thread_rng());
+    println!("Solvable in {} moves", par);
 
     game.moves.register(|moves| {
         println!("New number of moves: {}", moves);
@@ -289,13 +881,15 @@ fn main() -> Result<(), std::io::Error> {
     let stdin = std::io::stdin();
     let stdout = std::io::stdout();
     let mut buffer = String::new();
-    let mut read_usize = move |label| {
-        read_usize(&mut buffer, &stdin, &stdout, label)
+    let rows = game.rows;
+    let cols = game.cols;
+    let mut read_usize = move |label, bound| {
+        read_usize(&mut buffer, &stdin, &stdout, label, bound)
     };
     while !game.all_off() {
         println!("{}", game);
-        let row = read_usize("Row   : ")?;
-        let col = read_usize("Column: ")?;
+        let row = read_usize("Row   : ", rows)?;
+        let col = read_usize("Column: ", cols)?;
         game.make_move(row, col);
     }
 
@@ -305,10 +899,8 @@ fn main() -> Result<(), std::io::Error> {
 */
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /*
-    let mut game = Game::new_random(4, &mut rand::thread_rng());
-     */
-    let game = Rc::new(RefCell::new(Game::new_empty()));
+    let (game, par) = Game::new_random(5, 5, 8, &mut rand::thread_rng());
+    let game = Rc::new(RefCell::new(game));
     let doc = document();
 
     let body = match doc.body() {
@@ -316,23 +908,56 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Some(body) => body,
     };
 
+    let (rows, cols) = {
+        let game = RefCell::borrow(&game);
+        (game.rows, game.cols)
+    };
+
     let table = doc.create_element("table")?;
     body.append_child(&table);
-    for row in 0..5 {
+    for row in 0..rows {
         let tr = doc.create_element("tr")?;
         table.append_child(&tr);
-        for col in 0..5 {
+        for col in 0..cols {
             let td = doc.create_element("td")?;
             tr.append_child(&td);
             td.append_child(&light_widget(&doc, game.clone(), row, col)?);
         }
     }
 
+    let par_p = doc.create_element("p")?;
+    body.append_child(&par_p);
+    par_p.append_child(&doc.create_text_node(&format!("Solvable in {} moves", par)));
+
     let p = doc.create_element("p")?;
     body.append_child(&p);
     p.append_child(&doc.create_text_node("Total moves: "));
     p.append_child(&moves_widget(&doc, game.clone())?);
 
+    let state = Rc::new(RefCell::new(GameState::SinglePlayer));
+
+    let controls = doc.create_element("p")?;
+    body.append_child(&controls);
+    controls.append_child(&hint_widget(&doc, game.clone())?);
+    controls.append_child(&solve_widget(&doc, game.clone())?);
+    controls.append_child(&undo_widget(&doc, game.clone(), state.clone())?);
+    controls.append_child(&redo_widget(&doc, game.clone(), state.clone())?);
+
+    let share = doc.create_element("p")?;
+    body.append_child(&share);
+    share.append_child(&doc.create_text_node("Share puzzle: "));
+    share.append_child(&share_widget(&doc, game.clone())?);
+
+    let load = doc.create_element("p")?;
+    body.append_child(&load);
+    load.append_child(&doc.create_text_node("Load from code: "));
+    load.append_child(&load_widget(&doc, game.clone())?);
+
+    let race = doc.create_element("p")?;
+    body.append_child(&race);
+    race.append_child(&doc.create_text_node("Race server: "));
+    race.append_child(&multiplayer_widget(&doc, game.clone(), state.clone())?);
+
     Ok(())
 }
 
@@ -342,7 +967,7 @@ mod test {
 
     #[test]
     fn test_toggle_twice_is_noop() {
-        let game1 = Game::new_random(4, &mut rand::thread_rng());
+        let (game1, _) = Game::new_random(5, 5, 4, &mut rand::thread_rng());
         let mut game2 = game1.clone();
         assert_eq!(game1, game2);
         for i in 0..25 {
@@ -355,7 +980,7 @@ mod test {
 
     #[test]
     fn test_toggle_corner() {
-        let mut game = Game::new_empty();
+        let mut game = Game::new_empty(5, 5);
 
         assert_eq!(false, game.check_rc(0, 0));
         assert_eq!(false, game.check_rc(1, 0));
@@ -372,7 +997,7 @@ mod test {
 
     #[test]
     fn test_all_off() {
-        let mut game = Game::new_empty();
+        let mut game = Game::new_empty(5, 5);
         assert_eq!(true, game.all_off());
         game.toggle(0);
         assert_eq!(false, game.all_off());
@@ -380,7 +1005,136 @@ mod test {
 
     #[test]
     fn test_actually_random( ){
-        // odds of this happening are infinitesmally small
-        assert_eq!(false, Game::new_random(25, &mut rand::thread_rng()).all_off());
+        // difficulty 0 means "already solved", so anything else should
+        // leave at least one light on
+        let (game, par) = Game::new_random(5, 5, 8, &mut rand::thread_rng());
+        assert_eq!(8, par);
+        assert_eq!(false, game.all_off());
+    }
+
+    #[test]
+    fn test_solve_clears_the_board() {
+        let (mut game, par) = Game::new_random(5, 5, 8, &mut rand::thread_rng());
+        let solution = game.solve().expect("a random board is always solvable");
+        assert_eq!(par, solution.len());
+        for i in solution {
+            game.toggle(i);
+        }
+        assert!(game.all_off());
+    }
+
+    #[test]
+    fn test_solve_empty_board_is_already_solved() {
+        let game = Game::new_empty(5, 5);
+        assert_eq!(Some(vec![]), game.solve());
+        assert_eq!(None, game.hint());
+    }
+
+    #[test]
+    fn test_hint_is_part_of_the_solution() {
+        let (game, _) = Game::new_random(5, 5, 4, &mut rand::thread_rng());
+        let solution = game.solve().unwrap();
+        let hint = game.hint().unwrap();
+        assert!(solution.contains(&hint));
+    }
+
+    #[test]
+    fn test_non_square_board() {
+        let mut game = Game::new_empty(3, 7);
+        assert_eq!(false, game.check_rc(1, 6));
+        game.make_move(1, 6);
+        assert_eq!(true, game.check_rc(1, 6));
+        assert_eq!(true, game.check_rc(0, 6));
+        assert_eq!(true, game.check_rc(1, 5));
+        let moves: usize = *game.moves.borrow();
+        assert_eq!(1, moves);
+    }
+
+    #[test]
+    fn test_code_round_trip() {
+        let (game, _) = Game::new_random(5, 5, 6, &mut rand::thread_rng());
+        let code = game.to_code();
+        let loaded = Game::from_code(&code).unwrap();
+        assert_eq!(game, loaded);
+    }
+
+    #[test]
+    fn test_load_code_preserves_listeners() {
+        let mut game = Game::new_empty(5, 5);
+        game.toggle(0);
+        let code = game.to_code();
+
+        let mut fresh = Game::new_empty(5, 5);
+        let seen = Rc::new(RefCell::new(false));
+        let seen_clone = seen.clone();
+        fresh.lights[0].register(move |_| {
+            *RefCell::borrow_mut(&seen_clone) = true;
+        });
+        *RefCell::borrow_mut(&seen) = false;
+
+        fresh.load_code(&code).unwrap();
+        assert!(*RefCell::borrow(&seen));
+        assert_eq!(game, fresh);
+
+        // toggling the now-loaded board should still notify that listener
+        *RefCell::borrow_mut(&seen) = false;
+        game.toggle(0);
+        fresh.toggle(0);
+        assert!(*RefCell::borrow(&seen));
+    }
+
+    #[test]
+    fn test_parse_move() {
+        assert_eq!(Some((1, 2)), parse_move("1,2"));
+        assert_eq!(None, parse_move("nonsense"));
+    }
+
+    #[test]
+    fn test_make_move_sets_last_move() {
+        let mut game = Game::new_empty(5, 5);
+        assert_eq!(None, *game.last_move.borrow());
+        game.make_move(1, 2);
+        assert_eq!(Some((1, 2)), *game.last_move.borrow());
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let game1 = Game::new_empty(5, 5);
+        let mut game2 = game1.clone();
+
+        assert_eq!(false, game2.undo());
+        game2.make_move(1, 2);
+        assert_ne!(game1, game2);
+
+        assert!(game2.undo());
+        // `redo_stack` legitimately differs here -- undoing is what fills
+        // it -- so compare the playable state (lights + moves) rather than
+        // the whole `Game`.
+        assert_eq!(GameData::from(&game1), GameData::from(&game2));
+        assert_eq!(false, game2.undo());
+
+        assert!(game2.redo());
+        let moves: usize = *game2.moves.borrow();
+        assert_eq!(1, moves);
+        assert_eq!(true, game2.check_rc(1, 2));
+        assert_eq!(false, game2.redo());
+    }
+
+    #[test]
+    fn test_make_move_clears_redo_stack() {
+        let mut game = Game::new_empty(5, 5);
+        game.make_move(0, 0);
+        game.undo();
+        game.make_move(1, 1);
+        assert_eq!(false, game.redo());
+    }
+
+    #[test]
+    fn test_new_random_difficulty_is_minimal_solution_length() {
+        for difficulty in 0..=6 {
+            let (game, par) = Game::new_random(5, 5, difficulty, &mut rand::thread_rng());
+            assert_eq!(difficulty, par);
+            assert_eq!(difficulty, game.solve().unwrap().len());
+        }
     }
 }